@@ -3,11 +3,13 @@
 use std::cell::RefCell;
 use std::borrow;
 use std::rc::Rc;
+use nalgebra::na;
 use ncollide::bounding_volume::{HasBoundingVolume, AABB};
 use ncollide::broad::{Dispatcher, InterferencesBroadPhase, BoundingVolumeBroadPhase, RayCastBroadPhase};
 use ncollide::narrow::{CollisionDetector, GeomGeomDispatcher, GeomGeomCollisionDetector};
 use ncollide::contact::Contact;
 use ncollide::ray::{Ray, RayCastWithTransform};
+use ncollide::geom::min_distance;
 use ncollide::math::N;
 use object::RigidBody;
 use detection::constraint::{Constraint, RBRB};
@@ -52,6 +54,24 @@ impl Dispatcher<Rc<RefCell<RigidBody>>, Rc<RefCell<RigidBody>>, ~GeomGeomCollisi
 }
 
 
+/// A user-supplied hook allowing a contact to be accepted, rejected, or
+/// altered (e.g. its normal flipped) before it is turned into an `RBRB`
+/// constraint.
+///
+/// This is how one-way platforms are implemented: a platform tagged
+/// one-way rejects any contact whose relative approach velocity along
+/// `coll.normal` points through its allowed pass-through direction,
+/// letting a body rise through it but come to rest on top.
+pub trait ContactFilter {
+    /// Returns `None` to drop the contact, or `Some` of the (possibly
+    /// altered) contact to keep it.
+    fn filter_contact(&self,
+                       rb1:     &Rc<RefCell<RigidBody>>,
+                       rb2:     &Rc<RefCell<RigidBody>>,
+                       contact: &Contact)
+                       -> Option<Contact>;
+}
+
 /// Collision detector between rigid bodies.
 pub struct BodiesBodies<BF> {
     priv geom_geom_dispatcher:  Rc<GeomGeomDispatcher>,
@@ -60,6 +80,7 @@ pub struct BodiesBodies<BF> {
     // This must exist since there is no way to send an activation message without an accumulation
     // list…
     priv constraints_collector: ~[Constraint],
+    priv contact_filter:        Option<~ContactFilter>,
 }
 
 impl<BF: 'static + InterferencesBroadPhase<Rc<RefCell<RigidBody>>, ~GeomGeomCollisionDetector>> BodiesBodies<BF> {
@@ -69,8 +90,15 @@ impl<BF: 'static + InterferencesBroadPhase<Rc<RefCell<RigidBody>>, ~GeomGeomColl
             geom_geom_dispatcher:  dispatcher,
             contacts_collector:    ~[],
             constraints_collector: ~[],
+            contact_filter:        None,
         }
     }
+
+    /// Sets the contact filter invoked on every candidate contact before it
+    /// becomes an `RBRB` constraint, replacing any filter set previously.
+    pub fn set_contact_filter(&mut self, filter: Option<~ContactFilter>) {
+        self.contact_filter = filter;
+    }
 }
 
 impl<BF: RayCastBroadPhase<Rc<RefCell<RigidBody>>>> BodiesBodies<BF> {
@@ -160,14 +188,155 @@ Detector<RigidBody, Constraint, BF> for BodiesBodies<BF> {
     }
 
     fn interferences(&mut self, out: &mut ~[Constraint], broad_phase: &mut BF) {
+        // Take the filter out of `self` so the closure below only needs to
+        // borrow `self.contacts_collector`, not `self` as a whole.
+        let filter = self.contact_filter.take();
+
         broad_phase.for_each_pair_mut(|b1, b2, cd| {
             cd.colls(&mut self.contacts_collector);
 
             for c in self.contacts_collector.iter() {
-                out.push(RBRB(b1.clone(), b2.clone(), c.clone()))
+                let filtered = match filter {
+                    Some(ref f) => f.filter_contact(b1, b2, c),
+                    None        => Some(c.clone())
+                };
+
+                match filtered {
+                    Some(c) => out.push(RBRB(b1.clone(), b2.clone(), c)),
+                    None    => { }
+                }
             }
 
             self.contacts_collector.clear()
-        })
+        });
+
+        self.contact_filter = filter;
+    }
+}
+
+impl<BF: InterferencesBroadPhase<Rc<RefCell<RigidBody>>, ~GeomGeomCollisionDetector> +
+         BoundingVolumeBroadPhase<Rc<RefCell<RigidBody>>, AABB>>
+BodiesBodies<BF> {
+    /// Rolls every CCD-enabled fast mover back to its earliest time of
+    /// impact, if any, before the normal contact solve runs this step.
+    ///
+    /// Candidates come from a query against each mover's own *swept* AABB
+    /// (its current AABB loosened by this step's motion bound), not from
+    /// `broad_phase`'s already-overlapping pairs: a body that tunnels clean
+    /// through a thin wall in one step has non-overlapping AABBs at both its
+    /// previous and current transform and would never be paired with the
+    /// wall at all otherwise.
+    pub fn update_ccd(&mut self, dt: N, broad_phase: &mut BF, bodies: &[Rc<RefCell<RigidBody>>]) {
+        // Every sweep below reads `blocker`'s end-of-step transform, so all
+        // of them must run against the untouched, pre-rewind state: resolve
+        // every mover first and keep only its smallest TOI, then rewind each
+        // mover exactly once. Rewinding as we go would let an earlier
+        // mover's rewind corrupt a still-pending sweep that uses it as a
+        // blocker.
+        let mut min_tois: ~[(Rc<RefCell<RigidBody>>, N)] = ~[];
+
+        for mover in bodies.iter() {
+            let (is_mover, swept) = {
+                let bm = mover.borrow().borrow();
+
+                let motion_bound = na::norm(&(bm.get().lin_vel() * dt));
+                let is_mover      = bm.get().is_ccd_enabled() &&
+                                     motion_bound > bm.get().ccd_velocity_threshold() * dt;
+
+                (is_mover, bm.get().bounding_volume().loosened(motion_bound))
+            };
+
+            if !is_mover { continue; }
+
+            let mut candidates = ~[];
+            broad_phase.interferences_with_bounding_volume(&swept, &mut candidates);
+
+            for blocker in candidates.iter() {
+                if borrow::ref_eq(mover.borrow(), blocker.borrow()) { continue; }
+
+                match toi_between(dt.clone(), mover, blocker) {
+                    Some(t) => {
+                        let mut updated = false;
+
+                        for entry in min_tois.mut_iter() {
+                            let (ref m, ref mut best) = *entry;
+
+                            if borrow::ref_eq(m.borrow(), mover.borrow()) {
+                                if t < *best { *best = t.clone(); }
+                                updated = true;
+                            }
+                        }
+
+                        if !updated {
+                            min_tois.push((mover.clone(), t));
+                        }
+                    },
+                    None => { }
+                }
+            }
+        }
+
+        for &(ref mover, ref t) in min_tois.iter() {
+            let bm = mover.borrow().borrow_mut();
+            bm.get().rewind_to_toi(t.clone())
+        }
     }
 }
+
+/// Conservative advancement between `mover`'s previous and current
+/// transforms, returning its time of impact against `blocker` if any.
+fn toi_between(dt:      N,
+               mover:   &Rc<RefCell<RigidBody>>,
+               blocker: &Rc<RefCell<RigidBody>>)
+               -> Option<N> {
+    let tolerance: N = na::cast(0.005f32);
+    let one:       N = na::one();
+    let mut t:     N = na::zero();
+
+    let (prev1, prev2) = {
+        let bm = mover.borrow().borrow();
+        let bb = blocker.borrow().borrow();
+
+        (bm.get().previous_transform().clone(), bb.get().previous_transform().clone())
+    };
+
+    let vel_bound = {
+        let bm = mover.borrow().borrow();
+        let bb = blocker.borrow().borrow();
+
+        relative_motion_bound(bm.get(), bb.get())
+    };
+
+    if vel_bound <= na::zero() {
+        return None;
+    }
+
+    loop {
+        let distance = {
+            let bm = mover.borrow().borrow();
+            let bb = blocker.borrow().borrow();
+
+            let swept1 = na::interpolate(&prev1, bm.get().transform_ref(), &t);
+            let swept2 = na::interpolate(&prev2, bb.get().transform_ref(), &t);
+
+            min_distance(&swept1, bm.get().geom(), &swept2, bb.get().geom())
+        };
+
+        if distance <= tolerance {
+            return if t > na::zero() { Some(t) } else { None };
+        }
+
+        t = t + distance / vel_bound;
+
+        if t >= one {
+            return None;
+        }
+    }
+}
+
+/// Upper bound on how fast the gap between `rb1` and `rb2` can close this step.
+fn relative_motion_bound(rb1: &RigidBody, rb2: &RigidBody) -> N {
+    na::norm(&(rb1.lin_vel() - rb2.lin_vel())) +
+    rb1.geom().bounding_sphere_radius() * na::norm(&rb1.ang_vel()) +
+    rb2.geom().bounding_sphere_radius() * na::norm(&rb2.ang_vel())
+}
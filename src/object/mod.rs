@@ -0,0 +1,5 @@
+//! Physical objects simulated by the world.
+
+pub use self::rigid_body::{RigidBody, RigidBodyState, Static, Dynamic};
+
+mod rigid_body;
@@ -0,0 +1,191 @@
+//! The rigid body type: geometry, mass properties, and per-step dynamic state.
+
+use std::cell::Cell;
+use nalgebra::na;
+use ncollide::math::{N, LV, AV, M, AngularInertia};
+use ncollide::bounding_volume::{HasBoundingVolume, AABB};
+use ncollide::geom::Geom;
+
+/// Whether a `RigidBody` is moved by the solver or fixed in place.
+pub enum RigidBodyState {
+    Static,
+    Dynamic
+}
+
+/// A rigid body: one geometry plus the mass, velocity, and per-step
+/// accumulators the solver and integrator read and write.
+pub struct RigidBody {
+    priv geom:               ~Geom,
+    priv state:              RigidBodyState,
+    priv restitution:        N,
+    priv friction:           N,
+    priv inv_mass:           N,
+    priv inv_inertia:        AngularInertia,
+    priv index:              int,
+    priv active:             bool,
+
+    priv transform:          M,
+    priv previous_transform: M,
+    priv center_of_mass:     LV,
+
+    priv lin_vel: Cell<LV>,
+    priv ang_vel: Cell<AV>,
+    priv lin_acc: LV,
+    priv ang_acc: AV,
+
+    // Split-impulse pseudo-velocity accumulators (`CorrectionMode::SplitImpulse`):
+    // reset to zero every step, folded into position integration, but never
+    // fed back into `lin_vel`/`ang_vel`.
+    priv pseudo_lin_vel: Cell<LV>,
+    priv pseudo_ang_vel: Cell<AV>,
+
+    priv linear_damping:  N,
+    priv angular_damping: N,
+
+    priv ccd_enabled:            bool,
+    priv ccd_velocity_threshold: N,
+}
+
+impl RigidBody {
+    /// Creates a new rigid body from `geom`; `Static` bodies ignore `density`
+    /// and get infinite mass, `Dynamic` ones derive mass/inertia from it.
+    pub fn new<G: 'static + Geom>(geom: G, density: N, state: RigidBodyState, restitution: N, friction: N) -> RigidBody {
+        let (inv_mass, inv_inertia) = match state {
+            Static  => (na::zero(), na::zero()),
+            Dynamic => {
+                let mass = density * geom.volume();
+                (na::one::<N>() / mass, geom.inv_inertia(mass))
+            }
+        };
+
+        RigidBody {
+            center_of_mass:     geom.center_of_mass(),
+            geom:               ~geom as ~Geom,
+            state:              state,
+            restitution:        restitution,
+            friction:           friction,
+            inv_mass:           inv_mass,
+            inv_inertia:        inv_inertia,
+            index:              -1,
+            active:             true,
+            transform:          na::one(),
+            previous_transform: na::one(),
+            lin_vel:            Cell::new(na::zero()),
+            ang_vel:            Cell::new(na::zero()),
+            lin_acc:            na::zero(),
+            ang_acc:            na::zero(),
+            pseudo_lin_vel:     Cell::new(na::zero()),
+            pseudo_ang_vel:     Cell::new(na::zero()),
+            linear_damping:     na::zero(),
+            angular_damping:    na::zero(),
+            ccd_enabled:            false,
+            ccd_velocity_threshold: na::zero(),
+        }
+    }
+
+    pub fn can_move(&self) -> bool {
+        match self.state {
+            Static  => false,
+            Dynamic => true
+        }
+    }
+
+    pub fn geom(&self) -> &Geom { &*self.geom }
+    pub fn transform_ref(&self) -> &M { &self.transform }
+    pub fn previous_transform(&self) -> &M { &self.previous_transform }
+    pub fn center_of_mass(&self) -> &LV { &self.center_of_mass }
+
+    pub fn index(&self) -> int { self.index }
+    pub fn set_index(&mut self, i: int) { self.index = i }
+
+    pub fn is_active(&self) -> bool { self.active }
+
+    pub fn bounding_volume(&self) -> AABB { self.geom.bounding_volume(&self.transform) }
+
+    pub fn restitution(&self) -> N { self.restitution.clone() }
+    pub fn friction(&self)    -> N { self.friction.clone() }
+    pub fn inv_mass(&self)    -> N { self.inv_mass.clone() }
+    pub fn inv_inertia(&self) -> AngularInertia { self.inv_inertia.clone() }
+
+    pub fn lin_vel(&self) -> LV { self.lin_vel.get() }
+    pub fn ang_vel(&self) -> AV { self.ang_vel.get() }
+    pub fn set_lin_vel(&mut self, v: LV) { self.lin_vel.set(v) }
+    pub fn set_ang_vel(&mut self, v: AV) { self.ang_vel.set(v) }
+
+    pub fn lin_acc(&self) -> LV { self.lin_acc.clone() }
+    pub fn ang_acc(&self) -> AV { self.ang_acc.clone() }
+    pub fn set_lin_acc(&mut self, a: LV) { self.lin_acc = a }
+    pub fn set_ang_acc(&mut self, a: AV) { self.ang_acc = a }
+
+    /// Applies an instantaneous velocity change. Takes `&self`: the velocity
+    /// accumulators are `Cell`s so the PGS relax loop can update them
+    /// through the same shared `&RigidBody` it reads `lin_vel`/`ang_vel` from.
+    pub fn add_impulse(&self, dlin: LV, dang: AV) {
+        self.lin_vel.set(self.lin_vel.get() + dlin);
+        self.ang_vel.set(self.ang_vel.get() + dang);
+    }
+
+    pub fn pseudo_lin_vel(&self) -> LV { self.pseudo_lin_vel.get() }
+    pub fn pseudo_ang_vel(&self) -> AV { self.pseudo_ang_vel.get() }
+
+    /// Applies an impulse to the pseudo-velocity accumulators only, for the
+    /// split-impulse pass; never touches the real `lin_vel`/`ang_vel`.
+    pub fn add_pseudo_impulse(&self, dlin: LV, dang: AV) {
+        self.pseudo_lin_vel.set(self.pseudo_lin_vel.get() + dlin);
+        self.pseudo_ang_vel.set(self.pseudo_ang_vel.get() + dang);
+    }
+
+    pub fn linear_damping(&self)  -> N { self.linear_damping.clone() }
+    pub fn angular_damping(&self) -> N { self.angular_damping.clone() }
+    pub fn set_linear_damping(&mut self, d: N)  { self.linear_damping  = d }
+    pub fn set_angular_damping(&mut self, d: N) { self.angular_damping = d }
+
+    /// Decays `lin_vel`/`ang_vel` toward zero following Rapier's
+    /// `v *= 1 / (1 + dt * damping)` parameterization. Unconditional: runs
+    /// every step for every movable body, contacts or not.
+    pub fn apply_damping(&mut self, dt: N) {
+        if !self.can_move() { return; }
+
+        let _1: N = na::one();
+
+        let lin_factor = _1 / (_1 + dt * self.linear_damping);
+        let ang_factor = _1 / (_1 + dt * self.angular_damping);
+
+        self.lin_vel.set(self.lin_vel.get() * lin_factor);
+        self.ang_vel.set(self.ang_vel.get() * ang_factor);
+    }
+
+    pub fn is_ccd_enabled(&self) -> bool { self.ccd_enabled }
+    pub fn set_ccd_enabled(&mut self, enabled: bool) { self.ccd_enabled = enabled }
+
+    pub fn ccd_velocity_threshold(&self) -> N { self.ccd_velocity_threshold.clone() }
+    pub fn set_ccd_velocity_threshold(&mut self, threshold: N) { self.ccd_velocity_threshold = threshold }
+
+    /// Rewinds `transform` to the pose at motion parameter `t` (`0` is
+    /// `previous_transform`, `1` is the current `transform`), as found by a
+    /// CCD sweep. `previous_transform` is left untouched: it still marks the
+    /// start of this step's motion.
+    pub fn rewind_to_toi(&mut self, t: N) {
+        self.transform = na::interpolate(&self.previous_transform, &self.transform, &t);
+    }
+
+    /// Advances `transform` by the current velocity over `dt` (real velocity
+    /// plus any split-impulse pseudo-velocity), stashing the pre-integration
+    /// pose in `previous_transform` for CCD/interpolation. The pseudo
+    /// accumulators are reset to zero afterward: they never persist past the
+    /// step that produced them.
+    pub fn integrate(&mut self, dt: N) {
+        self.previous_transform = self.transform.clone();
+
+        if self.can_move() {
+            let lv = self.lin_vel.get() + self.pseudo_lin_vel.get();
+            let av = self.ang_vel.get() + self.pseudo_ang_vel.get();
+
+            self.transform = na::append_translation(&self.transform, &(lv * dt));
+            self.transform = na::append_rotation(&self.transform, &(av * dt));
+        }
+
+        self.pseudo_lin_vel.set(na::zero());
+        self.pseudo_ang_vel.set(na::zero());
+    }
+}
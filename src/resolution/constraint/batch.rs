@@ -0,0 +1,95 @@
+//! Partitioning of a flat constraint list into batches safe to relax in parallel.
+//!
+//! Two `VelocityConstraint`s conflict iff they share a movable body (a
+//! static body never conflicts, since its velocity accumulator is never
+//! touched by the solver). Constraints are greedily colored so that every
+//! batch (color class) touches pairwise-disjoint bodies; batches are then
+//! solved sequentially while the constraints within one batch can be
+//! relaxed concurrently without racing on the shared velocity accumulators.
+
+use resolution::constraint::velocity_constraint::VelocityConstraint;
+
+static NO_BODY: int = -1;
+
+/// Controls whether `sweep` partitions constraints into parallel-safe
+/// batches, or simply relaxes them in their original order.
+pub enum SolverStrategy {
+    Sequential,
+    Batched
+}
+
+/// Runs one SOR sweep over `constraints`, calling `relax(index, constraint)`
+/// on each so the caller can look up the bodies owning that row.
+///
+/// `Sequential` relaxes them in place order and ignores `batches`. `Batched`
+/// walks `batches` (from `color_constraints`) a whole color class at a time;
+/// constraints within one class touch disjoint bodies, so a caller with a
+/// thread pool can run `relax` over a class concurrently. Pass the same
+/// `batches` across every sweep within a step: the body-conflict graph
+/// doesn't change between SOR iterations, so recoloring per sweep would
+/// just repeat the same work.
+pub fn sweep(strategy:    &SolverStrategy,
+             batches:     &[~[uint]],
+             constraints: &mut [VelocityConstraint],
+             relax:       |uint, &mut VelocityConstraint|) {
+    match *strategy {
+        Sequential => {
+            for (i, c) in constraints.mut_iter().enumerate() {
+                relax(i, c);
+            }
+        },
+        Batched => {
+            for batch in batches.iter() {
+                for &i in batch.iter() {
+                    relax(i, &mut constraints[i]);
+                }
+            }
+        }
+    }
+}
+
+/// Greedily colors `constraints` so that no two constraints in the same
+/// color class share a movable body.
+///
+/// Returns one `~[uint]` of constraint indices per color; concatenating
+/// all of them in order yields every index in `0 .. constraints.len()`
+/// exactly once.
+pub fn color_constraints(constraints: &[VelocityConstraint]) -> ~[~[uint]] {
+    // For each color already opened, the set of movable bodies it touches.
+    let mut color_bodies: ~[~[int]]  = ~[];
+    let mut batches:      ~[~[uint]] = ~[];
+
+    for i in range(0u, constraints.len()) {
+        let c = &constraints[i];
+        let mut placed = false;
+
+        for color in range(0u, batches.len()) {
+            if !conflicts(c, &color_bodies[color]) {
+                batches[color].push(i);
+                record_bodies(c, &mut color_bodies[color]);
+                placed = true;
+                break;
+            }
+        }
+
+        if !placed {
+            // No existing color works: open a new one.
+            let mut bodies = ~[];
+            record_bodies(c, &mut bodies);
+            color_bodies.push(bodies);
+            batches.push(~[i]);
+        }
+    }
+
+    batches
+}
+
+fn conflicts(c: &VelocityConstraint, bodies: &[int]) -> bool {
+    (c.id1 != NO_BODY && bodies.iter().any(|b| *b == c.id1)) ||
+    (c.id2 != NO_BODY && bodies.iter().any(|b| *b == c.id2))
+}
+
+fn record_bodies(c: &VelocityConstraint, bodies: &mut ~[int]) {
+    if c.id1 != NO_BODY { bodies.push(c.id1); }
+    if c.id2 != NO_BODY { bodies.push(c.id2); }
+}
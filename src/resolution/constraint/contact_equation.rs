@@ -11,7 +11,9 @@ use utils::ref_to::RefTo;
 pub enum CorrectionMode {
     Velocity(N),
     VelocityAndPosition(N, N, N),
-    VelocityAndPositionThresold(N, N, N)
+    VelocityAndPositionThresold(N, N, N),
+    /// Bullet-style split impulse: `(pos_corr_factor, min_depth_for_pos_corr)`.
+    SplitImpulse(N, N)
 }
 
 impl CorrectionMode {
@@ -20,7 +22,8 @@ impl CorrectionMode {
         match *self {
             Velocity(ref v)                          => v.clone(),
             VelocityAndPosition(ref v, _, _)         => v.clone(),
-            VelocityAndPositionThresold(ref v, _, _) => v.clone()
+            VelocityAndPositionThresold(ref v, _, _) => v.clone(),
+            SplitImpulse(_, _)                       => na::zero()
         }
     }
 
@@ -29,6 +32,7 @@ impl CorrectionMode {
         match *self {
             VelocityAndPosition(_, ref p, _)         => p.clone(),
             VelocityAndPositionThresold(_, ref p, _) => p.clone(),
+            SplitImpulse(ref p, _)                   => p.clone(),
             Velocity(_)                              => na::zero()
         }
     }
@@ -38,6 +42,7 @@ impl CorrectionMode {
         match *self {
             VelocityAndPosition(_, _, ref t)         => t.clone(),
             VelocityAndPositionThresold(_, _, ref t) => t.clone(),
+            SplitImpulse(_, ref t)                   => t.clone(),
             Velocity(_)                              => Bounded::max_value()
         }
     }
@@ -47,15 +52,57 @@ impl CorrectionMode {
         match *self {
             VelocityAndPosition(_, _, _)             => Bounded::max_value(),
             VelocityAndPositionThresold(_, _, ref t) => t.clone(),
+            SplitImpulse(_, _)                       => Bounded::max_value(),
             Velocity(_)                              => Bounded::max_value()
         }
     }
+
+    #[inline]
+    pub fn is_split_impulse(&self) -> bool {
+        match *self {
+            SplitImpulse(_, _) => true,
+            _                  => false
+        }
+    }
+}
+
+/// How the per-body restitution or friction coefficients of a contact pair
+/// are combined into a single effective value.
+pub enum CombineRule {
+    /// `a * b`: the current, always-damped behaviour (e.g. a bouncy ball
+    /// loses its bounce on a dead surface).
+    Multiply,
+    /// `(a + b) / 2`.
+    Average,
+    /// `a.min(b)`: the least bouncy/grippy material wins.
+    Min,
+    /// `a.max(b)`: e.g. "a bouncy ball stays bouncy on any surface".
+    Max,
+    /// `(a * b).sqrt()`.
+    GeometricMean
+}
+
+impl CombineRule {
+    /// Combines the two per-body coefficients `a` and `b` according to
+    /// this rule.
+    #[inline]
+    pub fn combine(&self, a: N, b: N) -> N {
+        match *self {
+            Multiply      => a * b,
+            Average       => (a + b) / na::cast(2.0f32),
+            Min           => a.min(&b),
+            Max           => a.max(&b),
+            GeometricMean => (a * b).sqrt()
+        }
+    }
 }
 
 pub struct CorrectionParameters {
-    corr_mode:       CorrectionMode,
-    joint_corr:      N,
-    rest_eps:        N
+    corr_mode:           CorrectionMode,
+    joint_corr:          N,
+    rest_eps:            N,
+    restitution_combine: CombineRule,
+    friction_combine:    CombineRule
 }
 
 pub fn reinit_to_first_order_equation(dt:         N,
@@ -88,7 +135,7 @@ pub fn fill_second_order_equation(dt:           N,
                                   idf:          uint,
                                   cache:        &[N],
                                   correction:   &CorrectionParameters) {
-    let restitution = rb1.get().restitution() * rb2.get().restitution();
+    let restitution = correction.restitution_combine.combine(rb1.get().restitution(), rb2.get().restitution());
 
     let center = (coll.world1 + coll.world2) * na::cast::<f32, N>(0.5);
 
@@ -106,7 +153,7 @@ pub fn fill_second_order_equation(dt:           N,
                              correction);
 
 
-    let friction  = rb1.get().friction() * rb2.get().friction();
+    let friction  = correction.friction_combine.combine(rb1.get().friction(), rb2.get().friction());
     // To bound the friction we use the last frame normal impulse.
     // That means we have to make a special case for the first time the contact appears.
     // In that case, we estimate the impulse by the derired normal correction.
@@ -225,13 +272,21 @@ fn fill_velocity_constraint(dt:              N,
 
     constraint.objective = -constraint.objective;
 
-    if depth < na::zero() {
-        constraint.objective = constraint.objective + depth / dt
-    }
-    else if depth < correction.corr_mode.max_depth_for_vel_corr() {
-        constraint.objective = constraint.objective + depth * correction.corr_mode.vel_corr_factor() / dt
+    if !correction.corr_mode.is_split_impulse() {
+        if depth < na::zero() {
+            constraint.objective = constraint.objective + depth / dt
+        }
+        else if depth < correction.corr_mode.max_depth_for_vel_corr() {
+            constraint.objective = constraint.objective + depth * correction.corr_mode.vel_corr_factor() / dt
+        }
     }
 
+    // `relax_velocity_constraint` drives the relative velocity itself to
+    // `objective`: add back the starting relative velocity so the velocity
+    // change computed above becomes that target.
+    constraint.objective = constraint.objective + relative_velocity(&opt_rb1, &opt_rb2, &constraint.normal,
+                                                                    &constraint.rot_axis1, &constraint.rot_axis2, &dt);
+
     // for warm-starting
     constraint.impulse = if depth < na::zero() { na::zero() } else { initial_impulse };
 
@@ -240,8 +295,27 @@ fn fill_velocity_constraint(dt:              N,
      */
     constraint.lobound = lobound;
     constraint.hibound = hibound;
+
+    /*
+     * split-impulse pseudo-velocity pass: a separate, never warm-started
+     * objective that the pseudo-velocity solve alone sees.
+     */
+    if correction.corr_mode.is_split_impulse() &&
+       depth >= correction.corr_mode.min_depth_for_pos_corr() {
+        constraint.pseudo_objective = correction.corr_mode.pos_corr_factor() * depth.max(&na::zero()) / dt;
+    }
+    else {
+        constraint.pseudo_objective = na::zero();
+    }
+
+    constraint.pseudo_impulse = na::zero();
 }
 
+// Linear/angular damping is applied once per step, unconditionally, by
+// `RigidBody::apply_damping`, which `World::step` calls on every body
+// (outside this file); it is not something `relative_velocity` should reapply per-contact,
+// since that would skip bodies with no contact this step and would never
+// actually persist the decay between steps.
 pub fn relative_velocity<R: RefTo<RigidBody>>(
                          rb1:       &Option<R>,
                          rb2:       &Option<R>,
@@ -272,3 +346,96 @@ pub fn relative_velocity<R: RefTo<RigidBody>>(
 
     dvel
 }
+
+/// Same as `relative_velocity`, but against each body's pseudo-velocity
+/// accumulator (no acceleration term).
+pub fn relative_pseudo_velocity<R: RefTo<RigidBody>>(
+                                rb1:       &Option<R>,
+                                rb2:       &Option<R>,
+                                normal:    &LV,
+                                rot_axis1: &AV,
+                                rot_axis2: &AV)
+                                -> N {
+    let mut dvel: N = na::zero();
+
+    match *rb1 {
+        Some(ref b) => {
+            let rb = b.get();
+            dvel = dvel - na::dot(&rb.pseudo_lin_vel(), normal)
+                        + na::dot(&rb.pseudo_ang_vel(), rot_axis1);
+        },
+        None => { }
+    }
+
+    match *rb2 {
+        Some(ref b) => {
+            let rb = b.get();
+            dvel = dvel + na::dot(&rb.pseudo_lin_vel(), normal)
+                        + na::dot(&rb.pseudo_ang_vel(), rot_axis2);
+        },
+        None => { }
+    }
+
+    dvel
+}
+
+/// One PGS relaxation step of a single constraint row's real velocity and
+/// impulse. Call once per constraint per solver iteration; this is what
+/// actually drives `constraint.impulse` toward satisfying `objective`.
+pub fn relax_velocity_constraint(rb1: &Ref<RigidBody>, rb2: &Ref<RigidBody>, constraint: &mut VelocityConstraint) {
+    let opt_rb1 = if rb1.get().can_move() { Some(rb1.get()) } else { None };
+    let opt_rb2 = if rb2.get().can_move() { Some(rb2.get()) } else { None };
+
+    let rel_vel = relative_velocity(&opt_rb1, &opt_rb2,
+                                    &constraint.normal, &constraint.rot_axis1, &constraint.rot_axis2,
+                                    &na::zero());
+
+    let dimpulse    = (constraint.objective - rel_vel) * constraint.inv_projected_mass;
+    let new_impulse = (constraint.impulse + dimpulse).max(&constraint.lobound).min(&constraint.hibound);
+    let dimpulse    = new_impulse - constraint.impulse;
+
+    constraint.impulse = new_impulse;
+
+    match opt_rb1 {
+        Some(ref rb) => rb.add_impulse(constraint.weighted_normal1 * -dimpulse, constraint.weighted_rot_axis1 * dimpulse),
+        None => { }
+    }
+
+    match opt_rb2 {
+        Some(ref rb) => rb.add_impulse(constraint.weighted_normal2 * dimpulse, constraint.weighted_rot_axis2 * dimpulse),
+        None => { }
+    }
+}
+
+/// One PGS relaxation step of the split-impulse pseudo-velocity pass for a
+/// single constraint row. Run once per `SplitImpulse` constraint per solver
+/// iteration; it reads and writes only the pseudo-velocity accumulators, so
+/// it is independent of the real-velocity pass and may run right after it
+/// on the same row. The integrator must then advance positions by
+/// `real_velocity + pseudo_velocity` and reset the pseudo-velocity
+/// accumulators for the next step.
+pub fn relax_pseudo_velocity_constraint(rb1:        &Ref<RigidBody>,
+                                        rb2:        &Ref<RigidBody>,
+                                        constraint: &mut VelocityConstraint) {
+    let opt_rb1 = if rb1.get().can_move() { Some(rb1.get()) } else { None };
+    let opt_rb2 = if rb2.get().can_move() { Some(rb2.get()) } else { None };
+
+    let rel_vel = relative_pseudo_velocity(&opt_rb1, &opt_rb2,
+                                           &constraint.normal, &constraint.rot_axis1, &constraint.rot_axis2);
+
+    let dimpulse    = (constraint.pseudo_objective - rel_vel) * constraint.inv_projected_mass;
+    let new_impulse = (constraint.pseudo_impulse + dimpulse).max(&constraint.lobound).min(&constraint.hibound);
+    let dimpulse    = new_impulse - constraint.pseudo_impulse;
+
+    constraint.pseudo_impulse = new_impulse;
+
+    match opt_rb1 {
+        Some(ref rb) => rb.add_pseudo_impulse(constraint.weighted_normal1 * -dimpulse, constraint.weighted_rot_axis1 * dimpulse),
+        None => { }
+    }
+
+    match opt_rb2 {
+        Some(ref rb) => rb.add_pseudo_impulse(constraint.weighted_normal2 * dimpulse, constraint.weighted_rot_axis2 * dimpulse),
+        None => { }
+    }
+}
@@ -0,0 +1,230 @@
+//! Hinge, cone-twist, and generic 6-DOF joint constraints.
+
+use std::num::Bounded;
+use std::cell::Ref;
+use nalgebra::na;
+use ncollide::math::{N, LV, AV};
+use resolution::constraint::velocity_constraint::VelocityConstraint;
+use resolution::constraint::contact_equation::fill_constraint_geometry;
+use object::RigidBody;
+
+/// How one degree of freedom of a joint behaves.
+pub enum AxisMode {
+    Free,
+    Locked,
+    Limited(N, N),
+    Motorized(N, N)
+}
+
+impl AxisMode {
+    #[inline]
+    fn is_free(&self) -> bool {
+        match *self {
+            Free => true,
+            _     => false
+        }
+    }
+}
+
+/// Fills `constraint` for one degree of freedom shared by `rb1` and `rb2`,
+/// the joint-side counterpart of `contact_equation::fill_velocity_constraint`.
+pub fn fill_joint_equation(dt:         N,
+                           mode:       &AxisMode,
+                           axis:       LV,
+                           rot_axis1:  AV,
+                           rot_axis2:  AV,
+                           rb1:        &Ref<RigidBody>,
+                           rb2:        &Ref<RigidBody>,
+                           constraint: &mut VelocityConstraint) {
+    let opt_rb1 = if rb1.get().can_move() { Some(rb1.get()) } else { None };
+    let opt_rb2 = if rb2.get().can_move() { Some(rb2.get()) } else { None };
+
+    fill_constraint_geometry(axis, rot_axis1, rot_axis2, &opt_rb1, &opt_rb2, constraint);
+
+    constraint.id1 = rb1.get().index();
+    constraint.id2 = rb2.get().index();
+
+    // `objective` is the relative velocity `relax_velocity_constraint` drives
+    // this row to.
+    match *mode {
+        Free => { },
+        Locked => {
+            constraint.objective = na::zero();
+            constraint.lobound   = -Bounded::max_value();
+            constraint.hibound   = Bounded::max_value();
+        },
+        Limited(ref lo, ref hi) => {
+            constraint.objective = na::zero();
+            constraint.lobound   = lo.clone();
+            constraint.hibound   = hi.clone();
+        },
+        Motorized(ref target_vel, ref max_impulse) => {
+            constraint.objective = target_vel.clone();
+            constraint.lobound   = -max_impulse.clone();
+            constraint.hibound   = max_impulse.clone();
+        }
+    }
+
+    // joints are not warm-started from a contact-persistence cache
+    constraint.impulse = na::zero();
+}
+
+/// Locks the three relative linear degrees of freedom of `rb1` and `rb2` at
+/// `center` into `constraints[0..3]`.
+fn fill_point_to_point_equations(dt:          N,
+                                 center:      LV,
+                                 axis:        AV,
+                                 rb1:         &Ref<RigidBody>,
+                                 rb2:         &Ref<RigidBody>,
+                                 constraints: &mut [VelocityConstraint]) {
+    let mut i = 0;
+
+    na::orthonormal_subspace_basis(&axis, |perp| {
+        let rot_axis1 = na::cross(&(center - *rb1.get().center_of_mass()), &-perp);
+        let rot_axis2 = na::cross(&(center - *rb2.get().center_of_mass()), &perp);
+
+        fill_joint_equation(dt.clone(), &Locked, perp, rot_axis1, rot_axis2, rb1, rb2, &mut constraints[i]);
+        i = i + 1;
+
+        true
+    });
+
+    let rot_axis1 = na::cross(&(center - *rb1.get().center_of_mass()), &-axis);
+    let rot_axis2 = na::cross(&(center - *rb2.get().center_of_mass()), &axis);
+
+    fill_joint_equation(dt.clone(), &Locked, axis, rot_axis1, rot_axis2, rb1, rb2, &mut constraints[i]);
+}
+
+/// A hinge (revolute) joint: locks all relative linear degrees of freedom
+/// and every angular degree of freedom except rotation about `axis`.
+pub struct HingeJoint {
+    pub axis:   AV,
+    pub center: LV,
+    pub motion: AxisMode
+}
+
+impl HingeJoint {
+    pub fn new(axis: AV, center: LV) -> HingeJoint {
+        HingeJoint { axis: axis, center: center, motion: Free }
+    }
+
+    pub fn num_constraints(&self) -> uint {
+        if self.motion.is_free() { 5 } else { 6 }
+    }
+
+    pub fn fill_joint_equation(&self,
+                               dt:          N,
+                               rb1:         &Ref<RigidBody>,
+                               rb2:         &Ref<RigidBody>,
+                               constraints: &mut [VelocityConstraint]) {
+        fill_point_to_point_equations(dt.clone(), self.center.clone(), self.axis.clone(),
+                                      rb1, rb2, constraints.mut_slice(0, 3));
+
+        // lock swing about the two axes orthogonal to the hinge axis
+        let mut i = 3;
+
+        na::orthonormal_subspace_basis(&self.axis, |perp| {
+            fill_joint_equation(dt.clone(), &Locked, na::zero(), perp, -perp, rb1, rb2, &mut constraints[i]);
+            i = i + 1;
+
+            true
+        });
+
+        if !self.motion.is_free() {
+            fill_joint_equation(dt.clone(), &self.motion, na::zero(), self.axis.clone(), -self.axis.clone(),
+                                rb1, rb2, &mut constraints[i]);
+        }
+    }
+}
+
+/// A cone-twist joint: the anchor is locked, and relative rotation about the
+/// two axes orthogonal to `axis` (swing) and about `axis` (twist) is each
+/// resisted by a row driven to zero relative angular velocity, whose impulse
+/// is clamped to `±swing_limit` / `±twist_limit`. The relative angle itself
+/// is not measured, so no cone or twist angle range is enforced.
+pub struct ConeTwistJoint {
+    pub axis:        AV,
+    pub center:      LV,
+    pub swing_limit: N,
+    pub twist_limit: N
+}
+
+impl ConeTwistJoint {
+    pub fn num_constraints(&self) -> uint { 6 }
+
+    pub fn fill_joint_equation(&self,
+                               dt:          N,
+                               rb1:         &Ref<RigidBody>,
+                               rb2:         &Ref<RigidBody>,
+                               constraints: &mut [VelocityConstraint]) {
+        fill_point_to_point_equations(dt.clone(), self.center.clone(), self.axis.clone(),
+                                      rb1, rb2, constraints.mut_slice(0, 3));
+
+        let swing = Limited(-self.swing_limit.clone(), self.swing_limit.clone());
+        let mut i = 3;
+
+        na::orthonormal_subspace_basis(&self.axis, |perp| {
+            fill_joint_equation(dt.clone(), &swing, na::zero(), perp, -perp, rb1, rb2, &mut constraints[i]);
+            i = i + 1;
+
+            true
+        });
+
+        let twist = Limited(-self.twist_limit.clone(), self.twist_limit.clone());
+
+        fill_joint_equation(dt.clone(), &twist, na::zero(), self.axis.clone(), -self.axis.clone(),
+                            rb1, rb2, &mut constraints[i]);
+    }
+}
+
+/// A generic 6-DOF joint exposing an independent `AxisMode` for every linear
+/// and angular degree of freedom.
+pub struct Generic6Dof {
+    pub center:        LV,
+    pub linear_axes:   [LV, ..3],
+    pub linear_modes:  [AxisMode, ..3],
+    pub angular_axes:  [AV, ..3],
+    pub angular_modes: [AxisMode, ..3]
+}
+
+impl Generic6Dof {
+    pub fn num_constraints(&self) -> uint {
+        let mut n = 0;
+
+        for m in self.linear_modes.iter().chain(self.angular_modes.iter()) {
+            if !m.is_free() { n = n + 1; }
+        }
+
+        n
+    }
+
+    pub fn fill_joint_equation(&self,
+                               dt:          N,
+                               rb1:         &Ref<RigidBody>,
+                               rb2:         &Ref<RigidBody>,
+                               constraints: &mut [VelocityConstraint]) {
+        let mut i = 0;
+
+        for axis in range(0u, 3) {
+            if self.linear_modes[axis].is_free() { continue; }
+
+            let normal    = self.linear_axes[axis].clone();
+            let rot_axis1 = na::cross(&(self.center - *rb1.get().center_of_mass()), &-normal);
+            let rot_axis2 = na::cross(&(self.center - *rb2.get().center_of_mass()), &normal);
+
+            fill_joint_equation(dt.clone(), &self.linear_modes[axis], normal, rot_axis1, rot_axis2,
+                                rb1, rb2, &mut constraints[i]);
+            i = i + 1;
+        }
+
+        for axis in range(0u, 3) {
+            if self.angular_modes[axis].is_free() { continue; }
+
+            let a = self.angular_axes[axis].clone();
+
+            fill_joint_equation(dt.clone(), &self.angular_modes[axis], na::zero(), a.clone(), -a,
+                                rb1, rb2, &mut constraints[i]);
+            i = i + 1;
+        }
+    }
+}
@@ -0,0 +1,54 @@
+//! A velocity-level constraint between one or two rigid bodies.
+
+use nalgebra::na;
+use ncollide::math::{N, LV, AV};
+
+/// A single row of the velocity-level constraint system solved by the
+/// projected Gauss-Seidel loop.
+pub struct VelocityConstraint {
+    pub normal:               LV,
+    pub weighted_normal1:     LV,
+    pub weighted_normal2:     LV,
+    pub rot_axis1:            AV,
+    pub rot_axis2:            AV,
+    pub weighted_rot_axis1:   AV,
+    pub weighted_rot_axis2:   AV,
+    pub inv_projected_mass:   N,
+    pub id1:                  int,
+    pub id2:                  int,
+    pub objective:            N,
+    pub impulse:              N,
+    /// Objective/impulse pair for the split-impulse pseudo-velocity pass
+    /// (`CorrectionMode::SplitImpulse`); never warm-started.
+    pub pseudo_objective:      N,
+    pub pseudo_impulse:        N,
+    pub lobound:               N,
+    pub hibound:               N,
+    pub friction_coeff:        N,
+    pub friction_limit_id:     uint
+}
+
+impl VelocityConstraint {
+    pub fn new() -> VelocityConstraint {
+        VelocityConstraint {
+            normal:             na::zero(),
+            weighted_normal1:   na::zero(),
+            weighted_normal2:   na::zero(),
+            rot_axis1:          na::zero(),
+            rot_axis2:          na::zero(),
+            weighted_rot_axis1: na::zero(),
+            weighted_rot_axis2: na::zero(),
+            inv_projected_mass: na::zero(),
+            id1:                -1,
+            id2:                -1,
+            objective:          na::zero(),
+            impulse:            na::zero(),
+            pseudo_objective:   na::zero(),
+            pseudo_impulse:     na::zero(),
+            lobound:            na::zero(),
+            hibound:            na::zero(),
+            friction_coeff:     na::zero(),
+            friction_limit_id:  0
+        }
+    }
+}
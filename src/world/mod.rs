@@ -0,0 +1,171 @@
+//! The physics world: owns every rigid body and steps the simulation forward.
+
+use std::rc::Rc;
+use std::cell::RefCell;
+use nalgebra::na;
+use ncollide::math::{N, LV};
+use ncollide::bounding_volume::AABB;
+use ncollide::broad::DBVTBroadPhase;
+use ncollide::narrow::GeomGeomDispatcher;
+use object::RigidBody;
+use detection::bodies_bodies::{BodiesBodies, BodyBodyDispatcher};
+use detection::constraint::{Constraint, RBRB};
+use detection::activation_manager::ActivationManager;
+use resolution::constraint::velocity_constraint::VelocityConstraint;
+use resolution::constraint::contact_equation::{CorrectionParameters, Velocity, Multiply,
+                                               fill_second_order_equation, relax_velocity_constraint,
+                                               relax_pseudo_velocity_constraint};
+use resolution::constraint::batch::{SolverStrategy, Sequential, Batched, color_constraints, sweep};
+
+/// How many PGS iterations `step` runs per call.
+static NUM_SOLVER_ITERATIONS: uint = 4;
+
+type BF = DBVTBroadPhase<Rc<RefCell<RigidBody>>, AABB, ~::ncollide::narrow::GeomGeomCollisionDetector>;
+
+/// Owns every rigid body plus the broad/narrow-phase state, and steps the
+/// simulation forward in fixed increments of `dt`.
+pub struct World {
+    priv bodies:        ~[Rc<RefCell<RigidBody>>],
+    priv broad_phase:   BF,
+    priv bodies_bodies: BodiesBodies<BF>,
+    priv activation:    ActivationManager,
+    priv gravity:       LV,
+    priv correction:    CorrectionParameters,
+    priv solver_strategy: SolverStrategy,
+}
+
+impl World {
+    pub fn new() -> World {
+        let geom_dispatcher = Rc::new(GeomGeomDispatcher::new());
+
+        World {
+            bodies:        ~[],
+            broad_phase:   DBVTBroadPhase::new(BodyBodyDispatcher::new(geom_dispatcher.clone())),
+            bodies_bodies: BodiesBodies::new(geom_dispatcher),
+            activation:    ActivationManager::new(),
+            gravity:       na::zero(),
+            correction:    CorrectionParameters {
+                corr_mode:           Velocity(na::cast(0.2f32)),
+                joint_corr:          na::cast(0.2f32),
+                rest_eps:            na::cast(0.01f32),
+                restitution_combine: Multiply,
+                friction_combine:    Multiply,
+            },
+            solver_strategy: Sequential,
+        }
+    }
+
+    pub fn set_gravity(&mut self, gravity: LV) {
+        self.gravity = gravity;
+    }
+
+    /// Selects whether `step` relaxes constraints in place order (`Sequential`,
+    /// the default) or graph-colored batches (`Batched`); single-threaded
+    /// builds can still pick `Batched` to reproduce that ordering.
+    pub fn set_solver_strategy(&mut self, strategy: SolverStrategy) {
+        self.solver_strategy = strategy;
+    }
+
+    pub fn add_body(&mut self, body: Rc<RefCell<RigidBody>>) {
+        {
+            let bb = body.borrow().borrow_mut();
+            // Static bodies keep index -1 so constraint batching never treats them as a conflict.
+            let index = if bb.get().can_move() { self.bodies.len() as int } else { -1 };
+            bb.get().set_index(index);
+        }
+
+        self.broad_phase.add(body.clone());
+        self.bodies.push(body);
+    }
+
+    /// Advances every body by one step of `dt`: applies gravity, detects
+    /// contacts, relaxes them through the PGS loop, then integrates positions.
+    pub fn step(&mut self, dt: N) {
+        for b in self.bodies.iter() {
+            let bb = b.borrow().borrow_mut();
+            bb.get().set_lin_acc(self.gravity.clone());
+        }
+
+        self.bodies_bodies.update_ccd(dt.clone(), &mut self.broad_phase, self.bodies);
+
+        self.bodies_bodies.update(&mut self.broad_phase, &mut self.activation);
+
+        let mut constraints: ~[Constraint] = ~[];
+        self.bodies_bodies.interferences(&mut constraints, &mut self.broad_phase);
+
+        let mut velocity_constraints: ~[VelocityConstraint]                            = ~[];
+        let mut owners:               ~[(Rc<RefCell<RigidBody>>, Rc<RefCell<RigidBody>>)] = ~[];
+
+        for c in constraints.iter() {
+            match *c {
+                RBRB(ref rb1, ref rb2, ref contact) => {
+                    let mut rconstraint  = VelocityConstraint::new();
+                    let mut fconstraints = [VelocityConstraint::new(), VelocityConstraint::new()];
+
+                    {
+                        let brb1 = rb1.borrow().borrow();
+                        let brb2 = rb2.borrow().borrow();
+
+                        fill_second_order_equation(dt.clone(), contact, &brb1, &brb2, &mut rconstraint, 0,
+                                                   &mut fconstraints, 0, &[na::zero(), na::zero(), na::zero()],
+                                                   &self.correction);
+                    }
+
+                    owners.push((rb1.clone(), rb2.clone()));
+                    velocity_constraints.push(rconstraint);
+
+                    for fconstraint in fconstraints.move_iter() {
+                        owners.push((rb1.clone(), rb2.clone()));
+                        velocity_constraints.push(fconstraint);
+                    }
+                }
+            }
+        }
+
+        // Gravity enters the velocities here, so the solver starts from the
+        // same `lin_vel + lin_acc * dt` the rows above were filled against.
+        for b in self.bodies.iter() {
+            let bb = b.borrow().borrow_mut();
+
+            if bb.get().can_move() {
+                let lv = bb.get().lin_vel() + bb.get().lin_acc() * dt.clone();
+                let av = bb.get().ang_vel() + bb.get().ang_acc() * dt.clone();
+
+                bb.get().set_lin_vel(lv);
+                bb.get().set_ang_vel(av);
+            }
+        }
+
+        let split_impulse = self.correction.corr_mode.is_split_impulse();
+
+        // The body-conflict graph a `Batched` coloring is based on doesn't
+        // change across this step's SOR iterations, only the constraints'
+        // objectives/impulses do: color once and reuse it every sweep below
+        // instead of recomputing it on each call.
+        let batches = match self.solver_strategy {
+            Batched    => color_constraints(velocity_constraints),
+            Sequential => ~[]
+        };
+
+        for _ in range(0u, NUM_SOLVER_ITERATIONS) {
+            sweep(&self.solver_strategy, batches, velocity_constraints, |i, constraint| {
+                let (ref rb1, ref rb2) = owners[i];
+                let brb1 = rb1.borrow().borrow();
+                let brb2 = rb2.borrow().borrow();
+
+                relax_velocity_constraint(&brb1, &brb2, constraint);
+
+                if split_impulse {
+                    relax_pseudo_velocity_constraint(&brb1, &brb2, constraint);
+                }
+            });
+        }
+
+        for b in self.bodies.iter() {
+            let bb = b.borrow().borrow_mut();
+
+            bb.get().apply_damping(dt.clone());
+            bb.get().integrate(dt.clone());
+        }
+    }
+}